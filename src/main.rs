@@ -1,4 +1,10 @@
+// This is a teaching example: most of the API surface below (combinators, PipeFront/PipeBack,
+// PipeReader/PipeWriter, with_capacity) is exercised by the tests rather than by `main`, which
+// only needs a small slice of it to build the stdin/stdout pipeline.
+#![allow(dead_code)]
+
 use std::marker::PhantomData;
+use std::task::Waker;
 
 // A pipe is a "sans-IO" bidirectional communication channel.
 // To drive them, feed them input from both sides, and poll for output towards both sides.
@@ -12,11 +18,77 @@ use std::marker::PhantomData;
 //                    \_/___________________/
 //
 
+// An error a pipe can run into while turning input it already accepted into output, e.g.
+// malformed data from the outside world. Kept separate from the ok/error distinction a pipe's own
+// messages might already make (like BytesToLinesPipe's Result<String, String>): this is about the
+// pipe itself failing to produce output at all.
+#[derive(Debug, PartialEq)]
+enum PipeError {
+    InvalidUtf8(Vec<u8>),
+}
+
 trait Pipe<FrontInput, FrontOutput, BackOutput, BackInput> {
     fn handle_front_input(&mut self, message: FrontInput);
     fn handle_back_input(&mut self, message: BackInput);
-    fn poll_front_output(&mut self) -> Option<FrontOutput>;
-    fn poll_back_output(&mut self) -> Option<BackOutput>;
+    fn poll_front_output(&mut self) -> Option<Result<FrontOutput, PipeError>>;
+    fn poll_back_output(&mut self) -> Option<Result<BackOutput, PipeError>>;
+
+    // Pipes that buffer without bound can just rely on the defaults below. Pipes with a capacity
+    // override these to signal backpressure instead of growing forever: try_handle_*_input hands
+    // the message back when the relevant buffer is full, and front_ready/back_ready let a caller
+    // check before it even tries.
+    fn try_handle_front_input(&mut self, message: FrontInput) -> Result<(), FrontInput> {
+        self.handle_front_input(message);
+        Ok(())
+    }
+    fn try_handle_back_input(&mut self, message: BackInput) -> Result<(), BackInput> {
+        self.handle_back_input(message);
+        Ok(())
+    }
+    fn front_ready(&self) -> bool {
+        true
+    }
+    fn back_ready(&self) -> bool {
+        true
+    }
+}
+
+// Without this, a driver has no way to learn that new output became available and has to
+// spin-poll poll_front_output/poll_back_output. A WakablePipe lets it register a Waker on either
+// side instead, which the pipe wakes once handle_*_input enqueues something that side could turn
+// into output.
+//
+// That's a different condition from "this side has buffer capacity again", which is what a caller
+// blocked on try_handle_*_input/front_ready/back_ready actually needs to be woken by - that frees
+// up when the *other* side's poll_*_output drains a buffer, not when handle_*_input adds to one.
+// Reusing register_front_waker/register_back_waker for both would mean whichever registration
+// happened last clobbers the other, so a capacity-waiter could get permanently starved by an
+// output-waiter (or vice versa). Hence the separate register_*_capacity_waker methods below.
+trait WakablePipe<FrontInput, FrontOutput, BackOutput, BackInput>:
+    Pipe<FrontInput, FrontOutput, BackOutput, BackInput>
+{
+    fn register_front_waker(&mut self, waker: Waker);
+    fn register_back_waker(&mut self, waker: Waker);
+    fn register_front_capacity_waker(&mut self, waker: Waker);
+    fn register_back_capacity_waker(&mut self, waker: Waker);
+}
+
+// A store-latest-waker slot, in the spirit of the futures crate's `atomic_waker`: registering a
+// waker overwrites whatever was registered before, and waking consumes it, so each registration
+// fires at most once.
+#[derive(Default)]
+struct WakerSlot(Option<Waker>);
+
+impl WakerSlot {
+    fn register(&mut self, waker: Waker) {
+        self.0 = Some(waker);
+    }
+
+    fn wake(&mut self) {
+        if let Some(waker) = self.0.take() {
+            waker.wake();
+        }
+    }
 }
 
 // You can glue two pipes together if their interfaces match, creating in a new pipe.
@@ -35,8 +107,14 @@ where
 {
     a: A,
     b: B,
+    // A message already popped from one sub-pipe's output that the other sub-pipe wasn't ready to
+    // accept yet. Held here instead of dropped, and retried before pulling anything new, so
+    // backpressure on `b`'s front input (or `a`'s back input) doesn't silently vanish at the
+    // glue boundary.
+    pending_a_to_b: Option<AToB>,
+    pending_b_to_a: Option<BToA>,
     // This marker is required to be able to use all the generic paramaters.
-    _marker: PhantomData<(FrontInput, BackInput, BToA, BackOutput, FrontOutput, AToB)>,
+    _marker: PhantomData<(FrontInput, BackInput, BackOutput, FrontOutput)>,
 }
 
 impl<A, B, FrontInput, BackInput, BToA, BackOutput, FrontOutput, AToB>
@@ -49,6 +127,8 @@ where
         Self {
             a,
             b,
+            pending_a_to_b: None,
+            pending_b_to_a: None,
             _marker: PhantomData,
         }
     }
@@ -69,21 +149,488 @@ where
     fn handle_back_input(&mut self, message: BackInput) {
         self.b.handle_back_input(message);
     }
-    fn poll_back_output(&mut self) -> Option<BackOutput> {
-        while let Some(message) = self.a.poll_back_output() {
-            self.b.handle_front_input(message);
+    fn try_handle_front_input(&mut self, message: FrontInput) -> Result<(), FrontInput> {
+        self.a.try_handle_front_input(message)
+    }
+    fn try_handle_back_input(&mut self, message: BackInput) -> Result<(), BackInput> {
+        self.b.try_handle_back_input(message)
+    }
+    fn front_ready(&self) -> bool {
+        self.a.front_ready()
+    }
+    fn back_ready(&self) -> bool {
+        self.b.back_ready()
+    }
+    fn poll_back_output(&mut self) -> Option<Result<BackOutput, PipeError>> {
+        // Retry a message `b` rejected before, in order, before pulling anything new from `a`.
+        if let Some(message) = self.pending_a_to_b.take() {
+            if let Err(message) = self.b.try_handle_front_input(message) {
+                self.pending_a_to_b = Some(message);
+                return self.b.poll_back_output();
+            }
+        }
+        while self.b.front_ready() {
+            match self.a.poll_back_output() {
+                Some(Ok(message)) => {
+                    if let Err(message) = self.b.try_handle_front_input(message) {
+                        self.pending_a_to_b = Some(message);
+                        break;
+                    }
+                }
+                // `a` failed to produce back output at all; forward that straight through rather
+                // than swallowing it while draining into `b`.
+                Some(Err(error)) => return Some(Err(error)),
+                None => break,
+            }
         }
         self.b.poll_back_output()
     }
 
-    fn poll_front_output(&mut self) -> Option<FrontOutput> {
-        while let Some(message) = self.b.poll_front_output() {
-            self.a.handle_back_input(message);
+    fn poll_front_output(&mut self) -> Option<Result<FrontOutput, PipeError>> {
+        if let Some(message) = self.pending_b_to_a.take() {
+            if let Err(message) = self.a.try_handle_back_input(message) {
+                self.pending_b_to_a = Some(message);
+                return self.a.poll_front_output();
+            }
+        }
+        while self.a.back_ready() {
+            match self.b.poll_front_output() {
+                Some(Ok(message)) => {
+                    if let Err(message) = self.a.try_handle_back_input(message) {
+                        self.pending_b_to_a = Some(message);
+                        break;
+                    }
+                }
+                Some(Err(error)) => return Some(Err(error)),
+                None => break,
+            }
         }
         self.a.poll_front_output()
     }
 }
 
+// New back output can come from `a` directly, or indirectly: `a` wakes because it has something
+// for `b`, which then produces back output of its own. So both sides need the same waker.
+// Symmetrically for front output and `b`.
+impl<A, B, FrontInput, BackInput, BToA, BackOutput, FrontOutput, AToB>
+    WakablePipe<FrontInput, FrontOutput, BackOutput, BackInput>
+    for Glue<A, B, FrontInput, BackInput, BToA, BackOutput, FrontOutput, AToB>
+where
+    A: WakablePipe<FrontInput, FrontOutput, AToB, BToA>,
+    B: WakablePipe<AToB, BToA, BackOutput, BackInput>,
+{
+    fn register_front_waker(&mut self, waker: Waker) {
+        self.a.register_front_waker(waker.clone());
+        self.b.register_front_waker(waker);
+    }
+
+    fn register_back_waker(&mut self, waker: Waker) {
+        self.a.register_back_waker(waker.clone());
+        self.b.register_back_waker(waker);
+    }
+
+    // Glue's front_ready/back_ready just defer to `a`/`b` respectively (see above), so unlike the
+    // output wakers, capacity only needs registering on the one sub-pipe that owns it.
+    fn register_front_capacity_waker(&mut self, waker: Waker) {
+        self.a.register_front_capacity_waker(waker);
+    }
+
+    fn register_back_capacity_waker(&mut self, waker: Waker) {
+        self.b.register_back_capacity_waker(waker);
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Glue is the only way to compose pipes so far. PipeExt adds adapters that wrap a pipe and
+// transform its messages without writing a whole new Pipe impl, in the spirit of tokio_stream's
+// StreamExt.
+
+trait PipeExt<FrontInput, FrontOutput, BackOutput, BackInput>:
+    Pipe<FrontInput, FrontOutput, BackOutput, BackInput> + Sized
+{
+    fn map_front_output<F, NewFrontOutput>(self, f: F) -> MapFrontOutput<Self, F, FrontOutput>
+    where
+        F: FnMut(FrontOutput) -> NewFrontOutput,
+    {
+        MapFrontOutput {
+            inner: self,
+            f,
+            _marker: PhantomData,
+        }
+    }
+
+    fn map_back_output<F, NewBackOutput>(self, f: F) -> MapBackOutput<Self, F, BackOutput>
+    where
+        F: FnMut(BackOutput) -> NewBackOutput,
+    {
+        MapBackOutput {
+            inner: self,
+            f,
+            _marker: PhantomData,
+        }
+    }
+
+    fn map_front_input<F, NewFrontInput>(self, f: F) -> MapFrontInput<Self, F, NewFrontInput>
+    where
+        F: FnMut(NewFrontInput) -> FrontInput,
+    {
+        MapFrontInput {
+            inner: self,
+            f,
+            _marker: PhantomData,
+        }
+    }
+
+    fn map_back_input<F, NewBackInput>(self, f: F) -> MapBackInput<Self, F, NewBackInput>
+    where
+        F: FnMut(NewBackInput) -> BackInput,
+    {
+        MapBackInput {
+            inner: self,
+            f,
+            _marker: PhantomData,
+        }
+    }
+
+    fn filter_back_output<F>(self, predicate: F) -> FilterBackOutput<Self, F>
+    where
+        F: FnMut(&BackOutput) -> bool,
+    {
+        FilterBackOutput {
+            inner: self,
+            predicate,
+        }
+    }
+
+    fn inspect_back_output<F>(self, f: F) -> InspectBackOutput<Self, F>
+    where
+        F: FnMut(&BackOutput),
+    {
+        InspectBackOutput { inner: self, f }
+    }
+
+    // Makes both poll_*_output return None forever once they have returned None once, instead of
+    // possibly producing more output after a gap - handy once a pipe is combined with others that
+    // expect a "this side is done" signal to be final.
+    fn fuse(self) -> Fuse<Self> {
+        Fuse {
+            inner: self,
+            front_done: false,
+            back_done: false,
+        }
+    }
+}
+
+impl<P, FrontInput, FrontOutput, BackOutput, BackInput>
+    PipeExt<FrontInput, FrontOutput, BackOutput, BackInput> for P
+where
+    P: Pipe<FrontInput, FrontOutput, BackOutput, BackInput>,
+{
+}
+
+struct MapFrontOutput<P, F, FrontOutput> {
+    inner: P,
+    f: F,
+    _marker: PhantomData<FrontOutput>,
+}
+
+impl<P, F, FrontInput, FrontOutput, NewFrontOutput, BackOutput, BackInput>
+    Pipe<FrontInput, NewFrontOutput, BackOutput, BackInput> for MapFrontOutput<P, F, FrontOutput>
+where
+    P: Pipe<FrontInput, FrontOutput, BackOutput, BackInput>,
+    F: FnMut(FrontOutput) -> NewFrontOutput,
+{
+    fn handle_front_input(&mut self, message: FrontInput) {
+        self.inner.handle_front_input(message);
+    }
+    fn handle_back_input(&mut self, message: BackInput) {
+        self.inner.handle_back_input(message);
+    }
+    fn try_handle_front_input(&mut self, message: FrontInput) -> Result<(), FrontInput> {
+        self.inner.try_handle_front_input(message)
+    }
+    fn try_handle_back_input(&mut self, message: BackInput) -> Result<(), BackInput> {
+        self.inner.try_handle_back_input(message)
+    }
+    fn front_ready(&self) -> bool {
+        self.inner.front_ready()
+    }
+    fn back_ready(&self) -> bool {
+        self.inner.back_ready()
+    }
+    fn poll_front_output(&mut self) -> Option<Result<NewFrontOutput, PipeError>> {
+        self.inner
+            .poll_front_output()
+            .map(|result| result.map(&mut self.f))
+    }
+    fn poll_back_output(&mut self) -> Option<Result<BackOutput, PipeError>> {
+        self.inner.poll_back_output()
+    }
+}
+
+struct MapBackOutput<P, F, BackOutput> {
+    inner: P,
+    f: F,
+    _marker: PhantomData<BackOutput>,
+}
+
+impl<P, F, FrontInput, FrontOutput, BackOutput, NewBackOutput, BackInput>
+    Pipe<FrontInput, FrontOutput, NewBackOutput, BackInput> for MapBackOutput<P, F, BackOutput>
+where
+    P: Pipe<FrontInput, FrontOutput, BackOutput, BackInput>,
+    F: FnMut(BackOutput) -> NewBackOutput,
+{
+    fn handle_front_input(&mut self, message: FrontInput) {
+        self.inner.handle_front_input(message);
+    }
+    fn handle_back_input(&mut self, message: BackInput) {
+        self.inner.handle_back_input(message);
+    }
+    fn try_handle_front_input(&mut self, message: FrontInput) -> Result<(), FrontInput> {
+        self.inner.try_handle_front_input(message)
+    }
+    fn try_handle_back_input(&mut self, message: BackInput) -> Result<(), BackInput> {
+        self.inner.try_handle_back_input(message)
+    }
+    fn front_ready(&self) -> bool {
+        self.inner.front_ready()
+    }
+    fn back_ready(&self) -> bool {
+        self.inner.back_ready()
+    }
+    fn poll_front_output(&mut self) -> Option<Result<FrontOutput, PipeError>> {
+        self.inner.poll_front_output()
+    }
+    fn poll_back_output(&mut self) -> Option<Result<NewBackOutput, PipeError>> {
+        self.inner
+            .poll_back_output()
+            .map(|result| result.map(&mut self.f))
+    }
+}
+
+struct MapFrontInput<P, F, NewFrontInput> {
+    inner: P,
+    f: F,
+    _marker: PhantomData<NewFrontInput>,
+}
+
+impl<P, F, FrontInput, NewFrontInput, FrontOutput, BackOutput, BackInput>
+    Pipe<NewFrontInput, FrontOutput, BackOutput, BackInput> for MapFrontInput<P, F, NewFrontInput>
+where
+    P: Pipe<FrontInput, FrontOutput, BackOutput, BackInput>,
+    F: FnMut(NewFrontInput) -> FrontInput,
+{
+    fn handle_front_input(&mut self, message: NewFrontInput) {
+        self.inner.handle_front_input((self.f)(message));
+    }
+    fn handle_back_input(&mut self, message: BackInput) {
+        self.inner.handle_back_input(message);
+    }
+    // Checked against front_ready before transforming, so a rejected message comes back as the
+    // same NewFrontInput the caller passed in rather than something only `f` could have produced.
+    fn try_handle_front_input(&mut self, message: NewFrontInput) -> Result<(), NewFrontInput> {
+        if !self.front_ready() {
+            return Err(message);
+        }
+        self.handle_front_input(message);
+        Ok(())
+    }
+    fn try_handle_back_input(&mut self, message: BackInput) -> Result<(), BackInput> {
+        self.inner.try_handle_back_input(message)
+    }
+    fn front_ready(&self) -> bool {
+        self.inner.front_ready()
+    }
+    fn back_ready(&self) -> bool {
+        self.inner.back_ready()
+    }
+    fn poll_front_output(&mut self) -> Option<Result<FrontOutput, PipeError>> {
+        self.inner.poll_front_output()
+    }
+    fn poll_back_output(&mut self) -> Option<Result<BackOutput, PipeError>> {
+        self.inner.poll_back_output()
+    }
+}
+
+struct MapBackInput<P, F, NewBackInput> {
+    inner: P,
+    f: F,
+    _marker: PhantomData<NewBackInput>,
+}
+
+impl<P, F, FrontInput, FrontOutput, BackOutput, BackInput, NewBackInput>
+    Pipe<FrontInput, FrontOutput, BackOutput, NewBackInput> for MapBackInput<P, F, NewBackInput>
+where
+    P: Pipe<FrontInput, FrontOutput, BackOutput, BackInput>,
+    F: FnMut(NewBackInput) -> BackInput,
+{
+    fn handle_front_input(&mut self, message: FrontInput) {
+        self.inner.handle_front_input(message);
+    }
+    fn handle_back_input(&mut self, message: NewBackInput) {
+        self.inner.handle_back_input((self.f)(message));
+    }
+    fn try_handle_front_input(&mut self, message: FrontInput) -> Result<(), FrontInput> {
+        self.inner.try_handle_front_input(message)
+    }
+    // Same reasoning as MapFrontInput::try_handle_front_input, mirrored for the back side.
+    fn try_handle_back_input(&mut self, message: NewBackInput) -> Result<(), NewBackInput> {
+        if !self.back_ready() {
+            return Err(message);
+        }
+        self.handle_back_input(message);
+        Ok(())
+    }
+    fn front_ready(&self) -> bool {
+        self.inner.front_ready()
+    }
+    fn back_ready(&self) -> bool {
+        self.inner.back_ready()
+    }
+    fn poll_front_output(&mut self) -> Option<Result<FrontOutput, PipeError>> {
+        self.inner.poll_front_output()
+    }
+    fn poll_back_output(&mut self) -> Option<Result<BackOutput, PipeError>> {
+        self.inner.poll_back_output()
+    }
+}
+
+struct FilterBackOutput<P, F> {
+    inner: P,
+    predicate: F,
+}
+
+impl<P, F, FrontInput, FrontOutput, BackOutput, BackInput>
+    Pipe<FrontInput, FrontOutput, BackOutput, BackInput> for FilterBackOutput<P, F>
+where
+    P: Pipe<FrontInput, FrontOutput, BackOutput, BackInput>,
+    F: FnMut(&BackOutput) -> bool,
+{
+    fn handle_front_input(&mut self, message: FrontInput) {
+        self.inner.handle_front_input(message);
+    }
+    fn handle_back_input(&mut self, message: BackInput) {
+        self.inner.handle_back_input(message);
+    }
+    fn try_handle_front_input(&mut self, message: FrontInput) -> Result<(), FrontInput> {
+        self.inner.try_handle_front_input(message)
+    }
+    fn try_handle_back_input(&mut self, message: BackInput) -> Result<(), BackInput> {
+        self.inner.try_handle_back_input(message)
+    }
+    fn front_ready(&self) -> bool {
+        self.inner.front_ready()
+    }
+    fn back_ready(&self) -> bool {
+        self.inner.back_ready()
+    }
+    fn poll_front_output(&mut self) -> Option<Result<FrontOutput, PipeError>> {
+        self.inner.poll_front_output()
+    }
+    fn poll_back_output(&mut self) -> Option<Result<BackOutput, PipeError>> {
+        loop {
+            match self.inner.poll_back_output() {
+                Some(Ok(message)) if (self.predicate)(&message) => return Some(Ok(message)),
+                Some(Ok(_)) => continue,
+                Some(Err(error)) => return Some(Err(error)),
+                None => return None,
+            }
+        }
+    }
+}
+
+struct InspectBackOutput<P, F> {
+    inner: P,
+    f: F,
+}
+
+impl<P, F, FrontInput, FrontOutput, BackOutput, BackInput>
+    Pipe<FrontInput, FrontOutput, BackOutput, BackInput> for InspectBackOutput<P, F>
+where
+    P: Pipe<FrontInput, FrontOutput, BackOutput, BackInput>,
+    F: FnMut(&BackOutput),
+{
+    fn handle_front_input(&mut self, message: FrontInput) {
+        self.inner.handle_front_input(message);
+    }
+    fn handle_back_input(&mut self, message: BackInput) {
+        self.inner.handle_back_input(message);
+    }
+    fn try_handle_front_input(&mut self, message: FrontInput) -> Result<(), FrontInput> {
+        self.inner.try_handle_front_input(message)
+    }
+    fn try_handle_back_input(&mut self, message: BackInput) -> Result<(), BackInput> {
+        self.inner.try_handle_back_input(message)
+    }
+    fn front_ready(&self) -> bool {
+        self.inner.front_ready()
+    }
+    fn back_ready(&self) -> bool {
+        self.inner.back_ready()
+    }
+    fn poll_front_output(&mut self) -> Option<Result<FrontOutput, PipeError>> {
+        self.inner.poll_front_output()
+    }
+    fn poll_back_output(&mut self) -> Option<Result<BackOutput, PipeError>> {
+        let message = self.inner.poll_back_output();
+        if let Some(Ok(message)) = &message {
+            (self.f)(message);
+        }
+        message
+    }
+}
+
+struct Fuse<P> {
+    inner: P,
+    front_done: bool,
+    back_done: bool,
+}
+
+impl<P, FrontInput, FrontOutput, BackOutput, BackInput>
+    Pipe<FrontInput, FrontOutput, BackOutput, BackInput> for Fuse<P>
+where
+    P: Pipe<FrontInput, FrontOutput, BackOutput, BackInput>,
+{
+    fn handle_front_input(&mut self, message: FrontInput) {
+        self.inner.handle_front_input(message);
+    }
+    fn handle_back_input(&mut self, message: BackInput) {
+        self.inner.handle_back_input(message);
+    }
+    fn try_handle_front_input(&mut self, message: FrontInput) -> Result<(), FrontInput> {
+        self.inner.try_handle_front_input(message)
+    }
+    fn try_handle_back_input(&mut self, message: BackInput) -> Result<(), BackInput> {
+        self.inner.try_handle_back_input(message)
+    }
+    fn front_ready(&self) -> bool {
+        self.inner.front_ready()
+    }
+    fn back_ready(&self) -> bool {
+        self.inner.back_ready()
+    }
+    fn poll_front_output(&mut self) -> Option<Result<FrontOutput, PipeError>> {
+        if self.front_done {
+            return None;
+        }
+        let message = self.inner.poll_front_output();
+        if message.is_none() {
+            self.front_done = true;
+        }
+        message
+    }
+    fn poll_back_output(&mut self) -> Option<Result<BackOutput, PipeError>> {
+        if self.back_done {
+            return None;
+        }
+        let message = self.inner.poll_back_output();
+        if message.is_none() {
+            self.back_done = true;
+        }
+        message
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 // As an example, let's build two pipes.
 
@@ -98,33 +645,98 @@ use std::io::{Read, Write};
 struct BytesToLinesPipe {
     front_input: VecDeque<u8>,
     back_input: VecDeque<Result<String, String>>,
+    front_waker: WakerSlot,
+    back_waker: WakerSlot,
+    // Woken when front_input/back_input shrink, i.e. when a caller blocked on front_ready/
+    // back_ready becoming true again should be polled - see WakablePipe's doc comment.
+    front_capacity_waker: WakerSlot,
+    back_capacity_waker: WakerSlot,
+    // None means unbounded, matching the Default impl's behavior.
+    capacity: Option<usize>,
+}
+
+impl BytesToLinesPipe {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity: Some(capacity),
+            ..Default::default()
+        }
+    }
 }
 
 impl Pipe<Vec<u8>, Result<Vec<u8>, Vec<u8>>, String, Result<String, String>> for BytesToLinesPipe {
     fn handle_front_input(&mut self, bytes: Vec<u8>) {
-        self.front_input.extend(bytes)
+        self.front_input.extend(bytes);
+        self.back_waker.wake();
     }
     fn handle_back_input(&mut self, message: Result<String, String>) {
         self.back_input.push_back(message);
+        self.front_waker.wake();
+    }
+    fn try_handle_front_input(&mut self, bytes: Vec<u8>) -> Result<(), Vec<u8>> {
+        if !self.front_ready() {
+            return Err(bytes);
+        }
+        self.handle_front_input(bytes);
+        Ok(())
     }
-    fn poll_back_output(&mut self) -> Option<String> {
+    fn try_handle_back_input(
+        &mut self,
+        message: Result<String, String>,
+    ) -> Result<(), Result<String, String>> {
+        if !self.back_ready() {
+            return Err(message);
+        }
+        self.handle_back_input(message);
+        Ok(())
+    }
+    fn front_ready(&self) -> bool {
+        self.capacity.is_none_or(|cap| self.front_input.len() < cap)
+    }
+    fn back_ready(&self) -> bool {
+        self.capacity.is_none_or(|cap| self.back_input.len() < cap)
+    }
+    fn poll_back_output(&mut self) -> Option<Result<String, PipeError>> {
         if let Some(pos) = self.front_input.iter().position(|&x| x == b'\n') {
-            let message = self.front_input.drain(..pos).collect();
+            let message: Vec<u8> = self.front_input.drain(..pos).collect();
             self.front_input.drain(..1);
-            Some(String::from_utf8(message).unwrap())
+            self.front_capacity_waker.wake();
+            Some(
+                String::from_utf8(message)
+                    .map_err(|error| PipeError::InvalidUtf8(error.into_bytes())),
+            )
         } else {
             None
         }
     }
-    fn poll_front_output(&mut self) -> Option<Result<Vec<u8>, Vec<u8>>> {
+    fn poll_front_output(&mut self) -> Option<Result<Result<Vec<u8>, Vec<u8>>, PipeError>> {
         let into_bytes = |message: String| {
             let mut message = message.into_bytes();
             message.push(b'\n');
             message
         };
-        self.back_input
-            .pop_front()
-            .map(|message| message.map(into_bytes).map_err(into_bytes))
+        let message = self.back_input.pop_front();
+        if message.is_some() {
+            self.back_capacity_waker.wake();
+        }
+        message.map(|message| Ok(message.map(into_bytes).map_err(into_bytes)))
+    }
+}
+
+impl WakablePipe<Vec<u8>, Result<Vec<u8>, Vec<u8>>, String, Result<String, String>>
+    for BytesToLinesPipe
+{
+    fn register_front_waker(&mut self, waker: Waker) {
+        self.front_waker.register(waker);
+    }
+    fn register_back_waker(&mut self, waker: Waker) {
+        self.back_waker.register(waker);
+    }
+    fn register_front_capacity_waker(&mut self, waker: Waker) {
+        self.front_capacity_waker.register(waker);
+    }
+    fn register_back_capacity_waker(&mut self, waker: Waker) {
+        self.back_capacity_waker.register(waker);
     }
 }
 
@@ -135,99 +747,532 @@ impl Pipe<Vec<u8>, Result<Vec<u8>, Vec<u8>>, String, Result<String, String>> for
 struct StringsToNumbersPipe {
     back_output: VecDeque<i32>,
     front_output: VecDeque<Result<String, String>>,
+    front_waker: WakerSlot,
+    back_waker: WakerSlot,
+    // Woken when back_output/front_output shrink and front_ready()/back_ready() might now be
+    // true again - see WakablePipe's doc comment.
+    front_capacity_waker: WakerSlot,
+    back_capacity_waker: WakerSlot,
+    // None means unbounded, matching the Default impl's behavior.
+    capacity: Option<usize>,
+}
+
+impl StringsToNumbersPipe {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity: Some(capacity),
+            ..Default::default()
+        }
+    }
 }
 
 impl Pipe<String, Result<String, String>, i32, i32> for StringsToNumbersPipe {
     fn handle_front_input(&mut self, message: String) {
         if let Ok(n) = message.parse() {
             self.back_output.push_back(n);
+            self.back_waker.wake();
         } else {
             self.front_output
                 .push_back(Err(format!("Invalid number: {:?}", message)));
+            self.front_waker.wake();
         }
     }
     fn handle_back_input(&mut self, number: i32) {
         self.front_output.push_back(Ok(number.to_string()));
+        self.front_waker.wake();
+    }
+    fn try_handle_front_input(&mut self, message: String) -> Result<(), String> {
+        if !self.front_ready() {
+            return Err(message);
+        }
+        self.handle_front_input(message);
+        Ok(())
+    }
+    fn try_handle_back_input(&mut self, number: i32) -> Result<(), i32> {
+        if !self.back_ready() {
+            return Err(number);
+        }
+        self.handle_back_input(number);
+        Ok(())
+    }
+    fn front_ready(&self) -> bool {
+        // Either output queue could end up receiving the message, depending on whether it parses.
+        self.capacity
+            .is_none_or(|cap| self.back_output.len() < cap && self.front_output.len() < cap)
     }
-    fn poll_back_output(&mut self) -> Option<i32> {
-        self.back_output.pop_front()
+    fn back_ready(&self) -> bool {
+        self.capacity
+            .is_none_or(|cap| self.front_output.len() < cap)
     }
-    fn poll_front_output(&mut self) -> Option<Result<String, String>> {
-        self.front_output.pop_front()
+    fn poll_back_output(&mut self) -> Option<Result<i32, PipeError>> {
+        let message = self.back_output.pop_front();
+        if message.is_some() {
+            // Shrinks back_output, which is one of the two queues front_ready() watches.
+            self.front_capacity_waker.wake();
+        }
+        message.map(Ok)
+    }
+    fn poll_front_output(&mut self) -> Option<Result<Result<String, String>, PipeError>> {
+        let message = self.front_output.pop_front();
+        if message.is_some() {
+            // Shrinks front_output, which both front_ready() and back_ready() watch.
+            self.front_capacity_waker.wake();
+            self.back_capacity_waker.wake();
+        }
+        message.map(Ok)
     }
 }
 
-// We can now glue these pipes together to create a program that reads numbers from stdin,
-// processes the numbers, and sends the results to stdout. Here's a synchronous version.
+impl WakablePipe<String, Result<String, String>, i32, i32> for StringsToNumbersPipe {
+    fn register_front_waker(&mut self, waker: Waker) {
+        self.front_waker.register(waker);
+    }
+    fn register_back_waker(&mut self, waker: Waker) {
+        self.back_waker.register(waker);
+    }
+    fn register_front_capacity_waker(&mut self, waker: Waker) {
+        self.front_capacity_waker.register(waker);
+    }
+    fn register_back_capacity_waker(&mut self, waker: Waker) {
+        self.back_capacity_waker.register(waker);
+    }
+}
 
-fn main() {
-    let mut bytes_to_numbers_pipe =
-        Glue::new(BytesToLinesPipe::default(), StringsToNumbersPipe::default());
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Driving a pipe against real I/O always looks the same: fully drain poll_back_output and
+// poll_front_output before blocking on the next input read, so nothing produced by a handle_*_input
+// call is left sitting in a buffer. A PipeDriver captures that loop once, parameterized over
+// whatever reads front input, handles back output, and sinks front output for the caller.
 
-    let mut stdin = std::io::stdin().lock();
-    loop {
-        if let Some(n) = bytes_to_numbers_pipe.poll_back_output() {
-            let n = 2 * n;
-            bytes_to_numbers_pipe.handle_back_input(n);
-            continue;
+struct PipeDriver<P, FrontRead, BackHandle, FrontSink> {
+    pipe: P,
+    front_read: FrontRead,
+    back_handle: BackHandle,
+    front_sink: FrontSink,
+}
+
+impl<P, FrontRead, BackHandle, FrontSink> PipeDriver<P, FrontRead, BackHandle, FrontSink> {
+    fn new(pipe: P, front_read: FrontRead, back_handle: BackHandle, front_sink: FrontSink) -> Self {
+        Self {
+            pipe,
+            front_read,
+            back_handle,
+            front_sink,
         }
+    }
+}
 
-        match bytes_to_numbers_pipe.poll_front_output() {
-            Some(Ok(bytes)) => {
-                std::io::stdout().write_all(&bytes).unwrap();
-                continue;
+// The synchronous version: front_read and front_sink are ordinary closures, typically backed by
+// std::io::Read/Write underneath (see `main` below).
+impl<P, FrontRead, BackHandle, FrontSink> PipeDriver<P, FrontRead, BackHandle, FrontSink> {
+    fn run<FrontInput, FrontOutput, BackOutput, BackInput>(&mut self)
+    where
+        P: Pipe<FrontInput, FrontOutput, BackOutput, BackInput>,
+        FrontRead: FnMut() -> FrontInput,
+        BackHandle: FnMut(BackOutput) -> BackInput,
+        FrontSink: FnMut(FrontOutput),
+    {
+        loop {
+            match self.pipe.poll_back_output() {
+                Some(Ok(message)) => {
+                    let input = (self.back_handle)(message);
+                    self.pipe.handle_back_input(input);
+                    continue;
+                }
+                // A single place to observe decode failures etc. instead of every caller having to
+                // unwrap its way past them.
+                Some(Err(error)) => {
+                    eprintln!("pipe error on back output: {:?}", error);
+                    continue;
+                }
+                None => {}
             }
-            Some(Err(bytes)) => {
-                std::io::stderr().write_all(&bytes).unwrap();
-                continue;
+
+            match self.pipe.poll_front_output() {
+                Some(Ok(message)) => {
+                    (self.front_sink)(message);
+                    continue;
+                }
+                Some(Err(error)) => {
+                    eprintln!("pipe error on front output: {:?}", error);
+                    continue;
+                }
+                None => {}
             }
-            None => (),
-        }
 
-        let buf = &mut [0; 100];
-        let n = stdin.read(buf).unwrap();
-        bytes_to_numbers_pipe.handle_front_input(buf[..n].to_vec());
+            let message = (self.front_read)();
+            self.pipe.handle_front_input(message);
+        }
     }
 }
 
+// We can now glue these pipes together to create a program that reads numbers from stdin,
+// processes the numbers, and sends the results to stdout, without writing the pump loop by hand.
+
+fn main() {
+    let bytes_to_numbers_pipe =
+        Glue::new(BytesToLinesPipe::default(), StringsToNumbersPipe::default());
+
+    let mut stdin = std::io::stdin().lock();
+    let mut driver = PipeDriver::new(
+        bytes_to_numbers_pipe,
+        || {
+            let buf = &mut [0; 100];
+            let n = stdin.read(buf).unwrap();
+            buf[..n].to_vec()
+        },
+        |n| 2 * n,
+        |message: Result<Vec<u8>, Vec<u8>>| match message {
+            Ok(bytes) => std::io::stdout().write_all(&bytes).unwrap(),
+            Err(bytes) => std::io::stderr().write_all(&bytes).unwrap(),
+        },
+    );
+    driver.run();
+}
+
 // If we wanted, we could drive the same pipe asynchronously, by using async/await and Tokio.
 // That way, it would be easy do drive more than one pipe at the same time, by tokio::select!-ing
-// multiple event sources.
+// multiple event sources. PipeDriver::run_async mirrors the sync `run` exactly, just awaiting
+// the front_read and front_sink callbacks instead of blocking on them.
 
 /*
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
+// Generics are scoped to the method, exactly like the sync `run` above: the impl block itself is
+// only generic over `P, FrontRead, BackHandle, FrontSink` (the fields `PipeDriver` actually
+// stores), so anything else - FrontInput, FrontOutput, BackOutput, BackInput - has to live on
+// `run_async` or rustc rejects it as an unconstrained impl type parameter.
+impl<P, FrontRead, BackHandle, FrontSink> PipeDriver<P, FrontRead, BackHandle, FrontSink> {
+    // Named distinctly from the sync `run` above: both are inherent methods on the same
+    // `PipeDriver<P, FrontRead, BackHandle, FrontSink>` type, and differing where-clauses don't
+    // let two inherent methods share a name (that's an overload, which Rust doesn't support here).
+    //
+    // front_read/front_sink are bound by AsyncFnMut rather than `FnMut() -> impl Future`: the
+    // latter would need the returned future to borrow from the closure's captured state (e.g.
+    // `stdin`) across calls, which an ordinary FnMut can't express - AsyncFnMut ties the future's
+    // lifetime to the borrow of the call itself, which is exactly what an `async ||` closure is.
+    async fn run_async<FrontInput, FrontOutput, BackOutput, BackInput>(&mut self)
+    where
+        P: Pipe<FrontInput, FrontOutput, BackOutput, BackInput>,
+        FrontRead: AsyncFnMut() -> FrontInput,
+        BackHandle: FnMut(BackOutput) -> BackInput,
+        FrontSink: AsyncFnMut(FrontOutput),
+    {
+        loop {
+            match self.pipe.poll_back_output() {
+                Some(Ok(message)) => {
+                    let input = (self.back_handle)(message);
+                    self.pipe.handle_back_input(input);
+                    continue;
+                }
+                Some(Err(error)) => {
+                    eprintln!("pipe error on back output: {:?}", error);
+                    continue;
+                }
+                None => {}
+            }
+
+            match self.pipe.poll_front_output() {
+                Some(Ok(message)) => {
+                    (self.front_sink)(message).await;
+                    continue;
+                }
+                Some(Err(error)) => {
+                    eprintln!("pipe error on front output: {:?}", error);
+                    continue;
+                }
+                None => {}
+            }
+
+            let message = (self.front_read)().await;
+            self.pipe.handle_front_input(message);
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() {
-    let mut bytes_to_numbers_pipe =
+    let bytes_to_numbers_pipe =
         Glue::new(BytesToLinesPipe::default(), StringsToNumbersPipe::default());
 
     let mut stdin = tokio::io::stdin();
-    loop {
-        if let Some(n) = bytes_to_numbers_pipe.poll_back_output() {
-            let n = 2 * n;
-            bytes_to_numbers_pipe.handle_back_input(n);
-            continue;
+    let mut driver = PipeDriver::new(
+        bytes_to_numbers_pipe,
+        async || {
+            let mut buf = vec![0; 100];
+            let n = stdin.read(&mut buf).await.unwrap();
+            buf[..n].to_vec()
+        },
+        |n| 2 * n,
+        async |message: Result<Vec<u8>, Vec<u8>>| match message {
+            Ok(bytes) => tokio::io::stdout().write_all(&bytes).await.unwrap(),
+            Err(bytes) => tokio::io::stderr().write_all(&bytes).await.unwrap(),
+        },
+    );
+    driver.run_async().await;
+}
+*/
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// A pipe's two ends can also be exposed as a futures Stream + Sink each, so a pipe can plug into
+// the wider async ecosystem (select!, .forward(), StreamExt combinators, ...) instead of only
+// being driven by a PipeDriver. Both ends borrow the same underlying pipe, since handling input on
+// one side can produce output on the other.
+
+use futures::{Sink, Stream};
+use std::cell::RefCell;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+// Generic over the message types too, with a PhantomData marker, exactly like Glue above: the
+// Stream/Sink impls below need to name FrontInput/FrontOutput/BackOutput/BackInput, but neither
+// type stores a value of any of them directly (only `P`, behind the Rc<RefCell<..>>), so without
+// the marker rustc rejects the impls as unconstrained.
+struct PipeFront<P, FrontInput, FrontOutput, BackOutput, BackInput> {
+    shared: Rc<RefCell<P>>,
+    _marker: PhantomData<(FrontInput, FrontOutput, BackOutput, BackInput)>,
+}
+
+struct PipeBack<P, FrontInput, FrontOutput, BackOutput, BackInput> {
+    shared: Rc<RefCell<P>>,
+    _marker: PhantomData<(FrontInput, FrontOutput, BackOutput, BackInput)>,
+}
+
+impl<P, FrontInput, FrontOutput, BackOutput, BackInput> Stream
+    for PipeFront<P, FrontInput, FrontOutput, BackOutput, BackInput>
+where
+    P: WakablePipe<FrontInput, FrontOutput, BackOutput, BackInput> + Unpin,
+{
+    type Item = Result<FrontOutput, PipeError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut pipe = self.shared.borrow_mut();
+        match pipe.poll_front_output() {
+            Some(message) => Poll::Ready(Some(message)),
+            None => {
+                pipe.register_front_waker(cx.waker().clone());
+                Poll::Pending
+            }
         }
+    }
+}
 
-        match bytes_to_numbers_pipe.poll_front_output() {
-            Some(Ok(bytes)) => {
-                tokio::io::stdout().write_all(&bytes).await.unwrap();
-                continue;
+impl<P, FrontInput, FrontOutput, BackOutput, BackInput> Sink<FrontInput>
+    for PipeFront<P, FrontInput, FrontOutput, BackOutput, BackInput>
+where
+    P: WakablePipe<FrontInput, FrontOutput, BackOutput, BackInput> + Unpin,
+{
+    type Error = std::convert::Infallible;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let mut pipe = self.shared.borrow_mut();
+        if pipe.front_ready() {
+            Poll::Ready(Ok(()))
+        } else {
+            // Woken once poll_back_output drains the buffer front_ready watches, not once the other
+            // side sends something - see the comment on WakablePipe.
+            pipe.register_front_capacity_waker(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+
+    fn start_send(self: Pin<&mut Self>, message: FrontInput) -> Result<(), Self::Error> {
+        // The Sink contract guarantees poll_ready returned Ready(Ok(())) first.
+        self.shared.borrow_mut().handle_front_input(message);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<P, FrontInput, FrontOutput, BackOutput, BackInput> Stream
+    for PipeBack<P, FrontInput, FrontOutput, BackOutput, BackInput>
+where
+    P: WakablePipe<FrontInput, FrontOutput, BackOutput, BackInput> + Unpin,
+{
+    type Item = Result<BackOutput, PipeError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut pipe = self.shared.borrow_mut();
+        match pipe.poll_back_output() {
+            Some(message) => Poll::Ready(Some(message)),
+            None => {
+                pipe.register_back_waker(cx.waker().clone());
+                Poll::Pending
             }
-            Some(Err(bytes)) => {
-                tokio::io::stderr().write_all(&bytes).await.unwrap();
-                continue;
+        }
+    }
+}
+
+impl<P, FrontInput, FrontOutput, BackOutput, BackInput> Sink<BackInput>
+    for PipeBack<P, FrontInput, FrontOutput, BackOutput, BackInput>
+where
+    P: WakablePipe<FrontInput, FrontOutput, BackOutput, BackInput> + Unpin,
+{
+    type Error = std::convert::Infallible;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let mut pipe = self.shared.borrow_mut();
+        if pipe.back_ready() {
+            Poll::Ready(Ok(()))
+        } else {
+            // Same reasoning as PipeFront's Sink impl above, mirrored for the back side.
+            pipe.register_back_capacity_waker(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+
+    fn start_send(self: Pin<&mut Self>, message: BackInput) -> Result<(), Self::Error> {
+        // The Sink contract guarantees poll_ready returned Ready(Ok(())) first.
+        self.shared.borrow_mut().handle_back_input(message);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+// This turns `Glue::new(BytesToLinesPipe::default(), StringsToNumbersPipe::default())` into
+// something you can `.forward()`, `select!` over, or combine with `StreamExt` combinators:
+//
+//   let shared = Rc::new(RefCell::new(bytes_to_numbers_pipe));
+//   let front = PipeFront { shared: shared.clone(), _marker: PhantomData };
+//   let back = PipeBack { shared, _marker: PhantomData };
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// BytesToLinesPipe already does newline framing internally, so a byte-carrying pipe can also be
+// consumed the other way around: as an AsyncRead/AsyncBufRead, the inverse of what
+// tokio_util::io::StreamReader does for byte-chunk streams. PipeReader/PipeWriter let users
+// `tokio::io::copy` straight into/out of a pipe and read its output with `.lines()`.
+
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncWrite, ReadBuf};
+
+// Generic over FrontInput/BackOutput/BackInput with a PhantomData marker, for the same reason as
+// PipeFront/PipeBack above: the AsyncRead/AsyncBufRead impls below need to name them, but
+// PipeReader itself only stores the shared pipe and its leftover bytes. Marked with `fn() -> ..`
+// rather than bare `(..)`, unlike Glue's marker: poll_read/poll_fill_buf call self.get_mut(),
+// which needs Self: Unpin, and a bare tuple would make that conditional on FrontInput/BackOutput/
+// BackInput themselves being Unpin - a fn pointer is unconditionally Unpin instead.
+struct PipeReader<P, FrontInput, BackOutput, BackInput> {
+    shared: Rc<RefCell<P>>,
+    // Bytes already pulled out of the pipe that haven't been handed to the caller yet.
+    leftover: Vec<u8>,
+    #[allow(clippy::type_complexity)]
+    _marker: PhantomData<fn() -> (FrontInput, BackOutput, BackInput)>,
+}
+
+impl<P, FrontInput, BackOutput, BackInput> AsyncRead
+    for PipeReader<P, FrontInput, BackOutput, BackInput>
+where
+    P: WakablePipe<FrontInput, Result<Vec<u8>, Vec<u8>>, BackOutput, BackInput> + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        if this.leftover.is_empty() {
+            let mut pipe = this.shared.borrow_mut();
+            match pipe.poll_front_output() {
+                Some(Ok(Ok(bytes))) | Some(Ok(Err(bytes))) => this.leftover = bytes,
+                Some(Err(error)) => {
+                    return Poll::Ready(Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("{:?}", error),
+                    )));
+                }
+                None => {
+                    pipe.register_front_waker(cx.waker().clone());
+                    return Poll::Pending;
+                }
             }
-            None => (),
         }
 
-        let mut buf = vec![0; 100];
-        let n = stdin.read(&mut buf).await.unwrap();
-        bytes_to_numbers_pipe.handle_front_input(buf[..n].to_vec());
+        let n = this.leftover.len().min(buf.remaining());
+        buf.put_slice(&this.leftover[..n]);
+        this.leftover.drain(..n);
+        Poll::Ready(Ok(()))
     }
 }
-*/
+
+impl<P, FrontInput, BackOutput, BackInput> AsyncBufRead
+    for PipeReader<P, FrontInput, BackOutput, BackInput>
+where
+    P: WakablePipe<FrontInput, Result<Vec<u8>, Vec<u8>>, BackOutput, BackInput> + Unpin,
+{
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<&[u8]>> {
+        let this = self.get_mut();
+        if this.leftover.is_empty() {
+            let mut pipe = this.shared.borrow_mut();
+            match pipe.poll_front_output() {
+                Some(Ok(Ok(bytes))) | Some(Ok(Err(bytes))) => this.leftover = bytes,
+                Some(Err(error)) => {
+                    return Poll::Ready(Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("{:?}", error),
+                    )));
+                }
+                None => {
+                    pipe.register_front_waker(cx.waker().clone());
+                    return Poll::Pending;
+                }
+            }
+        }
+        Poll::Ready(Ok(&this.leftover))
+    }
+
+    fn consume(self: Pin<&mut Self>, amount: usize) {
+        self.get_mut().leftover.drain(..amount);
+    }
+}
+
+// The other half: pushes written byte slices into handle_front_input. Generic over
+// FrontOutput/BackOutput/BackInput with a PhantomData marker, same reasoning as PipeReader above.
+struct PipeWriter<P, FrontOutput, BackOutput, BackInput> {
+    shared: Rc<RefCell<P>>,
+    _marker: PhantomData<(FrontOutput, BackOutput, BackInput)>,
+}
+
+impl<P, FrontOutput, BackOutput, BackInput> AsyncWrite
+    for PipeWriter<P, FrontOutput, BackOutput, BackInput>
+where
+    P: Pipe<Vec<u8>, FrontOutput, BackOutput, BackInput> + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        self.shared.borrow_mut().handle_front_input(buf.to_vec());
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+// E.g. `tokio::io::copy(&mut pipe_writer, &mut some_sink).await`, or:
+//
+//   let reader = PipeReader { shared: shared.clone(), leftover: Vec::new(), _marker: PhantomData };
+//   let mut lines = tokio::io::AsyncBufReadExt::lines(reader);
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 // Tests are modular and fast, because they don't actually have to do IO.
@@ -241,36 +1286,311 @@ mod tests {
         let mut pipe = BytesToLinesPipe::default();
 
         pipe.handle_front_input(b"hello\nworld".to_vec());
-        assert_eq!(pipe.poll_back_output(), Some("hello".to_string()));
+        assert_eq!(pipe.poll_back_output(), Some(Ok("hello".to_string())));
         assert_eq!(pipe.poll_back_output(), None);
 
         pipe.handle_front_input(b"\n".to_vec());
-        assert_eq!(pipe.poll_back_output(), Some("world".to_string()));
+        assert_eq!(pipe.poll_back_output(), Some(Ok("world".to_string())));
 
         pipe.handle_back_input(Ok("hello".to_string()));
-        assert_eq!(pipe.poll_front_output(), Some(Ok(b"hello\n".to_vec())));
+        assert_eq!(pipe.poll_front_output(), Some(Ok(Ok(b"hello\n".to_vec()))));
         assert_eq!(pipe.poll_front_output(), None);
 
         pipe.handle_back_input(Err("hello".to_string()));
-        assert_eq!(pipe.poll_front_output(), Some(Err(b"hello\n".to_vec())));
+        assert_eq!(pipe.poll_front_output(), Some(Ok(Err(b"hello\n".to_vec()))));
         assert_eq!(pipe.poll_front_output(), None);
     }
 
+    #[test]
+    fn test_bytes_to_lines_pipe_invalid_utf8() {
+        let mut pipe = BytesToLinesPipe::default();
+
+        let invalid = vec![0x68, 0x65, 0xff, 0x6c, b'\n'];
+        pipe.handle_front_input(invalid.clone());
+        assert_eq!(
+            pipe.poll_back_output(),
+            Some(Err(PipeError::InvalidUtf8(vec![0x68, 0x65, 0xff, 0x6c])))
+        );
+    }
+
     #[test]
     fn test_strings_to_numbers_pipe() {
         let mut pipe = StringsToNumbersPipe::default();
 
         pipe.handle_front_input("42".to_string());
-        assert_eq!(pipe.poll_back_output(), Some(42));
+        assert_eq!(pipe.poll_back_output(), Some(Ok(42)));
         assert_eq!(pipe.poll_back_output(), None);
 
         pipe.handle_front_input("hello".to_string());
         // An error at the front.
         let front_output = pipe.poll_front_output();
         assert!(front_output.is_some());
-        assert!(front_output.unwrap().is_err());
+        assert!(front_output.unwrap().unwrap().is_err());
         // No output at the back.
         let back_output = pipe.poll_back_output();
         assert!(back_output.is_none());
     }
+
+    #[test]
+    fn test_bytes_to_lines_pipe_backpressure() {
+        let mut pipe = BytesToLinesPipe::with_capacity(2);
+
+        assert!(pipe.back_ready());
+        assert_eq!(pipe.try_handle_back_input(Ok("a".to_string())), Ok(()));
+        assert_eq!(pipe.try_handle_back_input(Ok("b".to_string())), Ok(()));
+        assert!(!pipe.back_ready());
+        assert_eq!(
+            pipe.try_handle_back_input(Ok("c".to_string())),
+            Err(Ok("c".to_string()))
+        );
+
+        // Draining frees up capacity again.
+        pipe.poll_front_output();
+        assert!(pipe.back_ready());
+        assert_eq!(pipe.try_handle_back_input(Ok("c".to_string())), Ok(()));
+    }
+
+    #[test]
+    fn test_bytes_to_lines_pipe_capacity_waker() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+        use std::task::Wake;
+
+        struct Flag(AtomicBool);
+        impl Wake for Flag {
+            fn wake(self: Arc<Self>) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+
+        let mut pipe = BytesToLinesPipe::with_capacity(1);
+        pipe.handle_back_input(Ok("a".to_string()));
+        assert!(!pipe.back_ready());
+
+        let flag = Arc::new(Flag(AtomicBool::new(false)));
+        pipe.register_back_capacity_waker(Waker::from(flag.clone()));
+
+        // Draining the buffer is what frees up capacity, not feeding the other side input.
+        pipe.handle_front_input(b"ignored".to_vec());
+        assert!(!flag.0.load(Ordering::SeqCst));
+
+        pipe.poll_front_output();
+        assert!(flag.0.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_bytes_to_lines_pipe_output_waker() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+        use std::task::Wake;
+
+        struct Flag(AtomicBool);
+        impl Wake for Flag {
+            fn wake(self: Arc<Self>) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+
+        let mut pipe = BytesToLinesPipe::default();
+        let flag = Arc::new(Flag(AtomicBool::new(false)));
+        pipe.register_back_waker(Waker::from(flag.clone()));
+
+        // handle_front_input is what could turn into back output, so it's what should wake the
+        // registered back waker - not e.g. handle_back_input, which feeds the other side.
+        pipe.handle_front_input(b"hello\n".to_vec());
+        assert!(flag.0.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_glue_propagates_waker_from_a_to_b() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+        use std::task::Wake;
+
+        struct Flag(AtomicBool);
+        impl Wake for Flag {
+            fn wake(self: Arc<Self>) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+
+        let mut glue = Glue::new(BytesToLinesPipe::default(), StringsToNumbersPipe::default());
+        let flag = Arc::new(Flag(AtomicBool::new(false)));
+        glue.register_back_waker(Waker::from(flag.clone()));
+
+        // `a` producing a line for `b` to turn into a number is what should wake the registered
+        // back waker here, even though the back output itself only appears once `b` has
+        // processed it - see Glue's WakablePipe impl doc comment.
+        glue.handle_front_input(b"42\n".to_vec());
+        assert!(flag.0.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_glue_backpressure_retains_surplus() {
+        let mut glue = Glue::new(
+            BytesToLinesPipe::default(),
+            StringsToNumbersPipe::with_capacity(1),
+        );
+
+        // `a` hands off two lines, but `b` can only hold one number at a time.
+        glue.handle_front_input(b"1\n2\n".to_vec());
+
+        // The surplus ("2") stays buffered in `a` rather than being dropped while `b` is full.
+        assert_eq!(glue.poll_back_output(), Some(Ok(1)));
+        assert_eq!(glue.poll_back_output(), Some(Ok(2)));
+        assert_eq!(glue.poll_back_output(), None);
+    }
+
+    #[test]
+    fn test_map_back_output() {
+        let mut pipe = BytesToLinesPipe::default().map_back_output(|s: String| s.len());
+
+        pipe.handle_front_input(b"hello\n".to_vec());
+        assert_eq!(pipe.poll_back_output(), Some(Ok(5)));
+    }
+
+    #[test]
+    fn test_combinator_preserves_backpressure() {
+        // Wrapping a capacity-bounded pipe in a combinator must not silently fall back to the
+        // trait's unbounded defaults for front_ready/back_ready/try_handle_*_input.
+        let mut pipe = BytesToLinesPipe::with_capacity(2).map_back_output(|s: String| s.len());
+
+        assert!(pipe.back_ready());
+        assert_eq!(pipe.try_handle_back_input(Ok("a".to_string())), Ok(()));
+        assert_eq!(pipe.try_handle_back_input(Ok("b".to_string())), Ok(()));
+        assert!(!pipe.back_ready());
+        assert_eq!(
+            pipe.try_handle_back_input(Ok("c".to_string())),
+            Err(Ok("c".to_string()))
+        );
+
+        pipe.poll_front_output();
+        assert!(pipe.back_ready());
+        assert_eq!(pipe.try_handle_back_input(Ok("c".to_string())), Ok(()));
+    }
+
+    #[test]
+    fn test_map_front_output() {
+        let mut pipe = StringsToNumbersPipe::default()
+            .map_front_output(|result: Result<String, String>| result.unwrap_or_else(|e| e));
+
+        pipe.handle_back_input(7);
+        assert_eq!(pipe.poll_front_output(), Some(Ok("7".to_string())));
+    }
+
+    #[test]
+    fn test_map_front_input() {
+        let mut pipe = StringsToNumbersPipe::default().map_front_input(|n: i32| n.to_string());
+
+        pipe.handle_front_input(42);
+        assert_eq!(pipe.poll_back_output(), Some(Ok(42)));
+    }
+
+    #[test]
+    fn test_map_back_input() {
+        let mut pipe =
+            StringsToNumbersPipe::default().map_back_input(|s: String| s.parse().unwrap());
+
+        pipe.handle_back_input("9".to_string());
+        assert_eq!(pipe.poll_front_output(), Some(Ok(Ok("9".to_string()))));
+    }
+
+    #[test]
+    fn test_filter_back_output() {
+        let mut pipe = StringsToNumbersPipe::default().filter_back_output(|n: &i32| n % 2 == 0);
+
+        pipe.handle_front_input("3".to_string());
+        pipe.handle_front_input("4".to_string());
+        assert_eq!(pipe.poll_back_output(), Some(Ok(4)));
+        assert_eq!(pipe.poll_back_output(), None);
+    }
+
+    #[test]
+    fn test_inspect_back_output() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let seen_clone = seen.clone();
+        let mut pipe = StringsToNumbersPipe::default()
+            .inspect_back_output(move |n: &i32| seen_clone.borrow_mut().push(*n));
+
+        pipe.handle_front_input("5".to_string());
+        assert_eq!(pipe.poll_back_output(), Some(Ok(5)));
+        assert_eq!(*seen.borrow(), vec![5]);
+    }
+
+    #[test]
+    fn test_fuse() {
+        let mut pipe = StringsToNumbersPipe::default().fuse();
+
+        assert_eq!(pipe.poll_back_output(), None);
+        pipe.handle_front_input("42".to_string());
+        // Once poll_back_output has returned None, it's done for good, even though there's now a
+        // message waiting.
+        assert_eq!(pipe.poll_back_output(), None);
+    }
+
+    #[test]
+    fn test_pipe_front_back_stream_sink_roundtrip() {
+        use futures::executor::block_on;
+        use futures::{SinkExt, StreamExt};
+
+        let glue = Glue::new(BytesToLinesPipe::default(), StringsToNumbersPipe::default());
+        let shared = Rc::new(RefCell::new(glue));
+        let mut front = PipeFront {
+            shared: shared.clone(),
+            _marker: PhantomData,
+        };
+        let mut back = PipeBack {
+            shared,
+            _marker: PhantomData,
+        };
+
+        block_on(async {
+            // Send a line into the front Sink, read the parsed number back out of the back Stream.
+            front.send(b"42\n".to_vec()).await.unwrap();
+            assert_eq!(back.next().await, Some(Ok(42)));
+
+            // And the other direction: a number sent into the back Sink comes back out as a line
+            // on the front Stream.
+            back.send(7).await.unwrap();
+            assert_eq!(front.next().await, Some(Ok(Ok(b"7\n".to_vec()))));
+        });
+    }
+
+    #[test]
+    fn test_pipe_reader_writer_copy_and_lines() {
+        use futures::executor::block_on;
+        use tokio::io::AsyncBufReadExt;
+
+        let shared = Rc::new(RefCell::new(BytesToLinesPipe::default()));
+        let mut writer = PipeWriter {
+            shared: shared.clone(),
+            _marker: PhantomData,
+        };
+        let reader = PipeReader {
+            shared: shared.clone(),
+            leftover: Vec::new(),
+            _marker: PhantomData,
+        };
+        let mut lines = reader.lines();
+
+        block_on(async {
+            // tokio::io::copy writes the source bytes into the pipe via PipeWriter, which land at
+            // the back as a parsed line.
+            tokio::io::copy(&mut b"hello\n".as_slice(), &mut writer)
+                .await
+                .unwrap();
+            assert_eq!(
+                shared.borrow_mut().poll_back_output(),
+                Some(Ok("hello".to_string()))
+            );
+
+            // Feeding that line back in as a back_input produces front output bytes that
+            // PipeReader::lines() can read back out, round-tripping through both halves.
+            shared.borrow_mut().handle_back_input(Ok("hello".to_string()));
+            assert_eq!(lines.next_line().await.unwrap(), Some("hello".to_string()));
+        });
+    }
 }